@@ -0,0 +1,115 @@
+use sha2::{Digest as _, Sha256};
+use sha3::Keccak256;
+
+/// Domain tag prepended to leaf data before hashing, so a leaf hash can never
+/// be replayed as an internal node hash (and vice versa).
+pub(crate) const MERKLE_LEAF_PREFIX: u8 = 0x00;
+/// Domain tag prepended to concatenated child hashes before hashing.
+pub(crate) const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Abstracts the hash function a `MerkleTree` (in the binary's `main.rs`)
+/// and the library's sparse and incremental trees are built on, so the
+/// same tree/proof logic can serve ecosystems that standardize on different
+/// digests (Ethereum's Keccak-256, Bitcoin's SHA-256, ...) instead of baking
+/// SHA-256 in everywhere.
+pub trait Hasher: Clone + std::fmt::Debug {
+    /// Raw digest bytes. Kept as bytes rather than a hex `String` so
+    /// building up a tree doesn't pay a hex encode/decode cost at every
+    /// level; hex is only for display (see `reduce_string` in `main.rs`).
+    /// `Hash` lets digests live in sets/maps, e.g. to dedupe shared sibling
+    /// hashes when building a `MultiProof` in `main.rs`.
+    type Output: AsRef<[u8]> + Clone + Eq + std::hash::Hash + std::fmt::Debug + Default;
+
+    fn hash_leaf(data: &[u8], domain_separated: bool) -> Self::Output;
+    fn hash_nodes(left: &Self::Output, right: &Self::Output, domain_separated: bool) -> Self::Output;
+
+    /// The all-zero sentinel used to mark an absent leaf, e.g. the base case
+    /// of `SparseMerkleTree`'s zero hashes or an `IncrementalMerkleTree`'s
+    /// not-yet-appended subtrees. Same byte length as a real `hash_leaf`/
+    /// `hash_nodes` output, so it can stand in for one structurally.
+    fn empty_output() -> Self::Output;
+}
+
+/// The crate's original hasher: plain SHA-256, serving Bitcoin-style
+/// ecosystems.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    type Output = Vec<u8>;
+
+    fn hash_leaf(data: &[u8], domain_separated: bool) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        if domain_separated {
+            hasher.update([MERKLE_LEAF_PREFIX]);
+        }
+        hasher.update(data);
+        hasher.finalize().to_vec()
+    }
+
+    fn hash_nodes(left: &Vec<u8>, right: &Vec<u8>, domain_separated: bool) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        if domain_separated {
+            hasher.update([MERKLE_NODE_PREFIX]);
+        }
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().to_vec()
+    }
+
+    fn empty_output() -> Vec<u8> {
+        vec![0u8; 32]
+    }
+}
+
+/// Keccak-256, as used by Ethereum (note: distinct from NIST SHA3-256).
+#[derive(Clone, Copy, Debug)]
+pub struct Keccak256Hasher;
+
+impl Hasher for Keccak256Hasher {
+    type Output = Vec<u8>;
+
+    fn hash_leaf(data: &[u8], domain_separated: bool) -> Vec<u8> {
+        let mut hasher = Keccak256::new();
+        if domain_separated {
+            hasher.update([MERKLE_LEAF_PREFIX]);
+        }
+        hasher.update(data);
+        hasher.finalize().to_vec()
+    }
+
+    fn hash_nodes(left: &Vec<u8>, right: &Vec<u8>, domain_separated: bool) -> Vec<u8> {
+        let mut hasher = Keccak256::new();
+        if domain_separated {
+            hasher.update([MERKLE_NODE_PREFIX]);
+        }
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().to_vec()
+    }
+
+    fn empty_output() -> Vec<u8> {
+        vec![0u8; 32]
+    }
+}
+
+/// SHA-256 truncated to its first 20 bytes, for callers that want smaller
+/// proofs and can accept a reduced security margin against collisions.
+#[derive(Clone, Copy, Debug)]
+pub struct TruncatedSha256Hasher;
+
+impl Hasher for TruncatedSha256Hasher {
+    type Output = Vec<u8>;
+
+    fn hash_leaf(data: &[u8], domain_separated: bool) -> Vec<u8> {
+        Sha256Hasher::hash_leaf(data, domain_separated)[..20].to_vec()
+    }
+
+    fn hash_nodes(left: &Vec<u8>, right: &Vec<u8>, domain_separated: bool) -> Vec<u8> {
+        Sha256Hasher::hash_nodes(left, right, domain_separated)[..20].to_vec()
+    }
+
+    fn empty_output() -> Vec<u8> {
+        vec![0u8; 20]
+    }
+}
@@ -0,0 +1,228 @@
+use crate::hasher::{Hasher, Sha256Hasher};
+
+/// Fixed tree depth, i.e. capacity for up to 2^32 leaves. Unlike the
+/// binary's `MerkleTree` (in `main.rs`), which rebuilds from a full `Vec` on every
+/// `create`, this depth is fixed up front so new leaves can be appended one
+/// at a time without ever re-reading earlier leaves.
+const DEPTH: usize = 32;
+
+/// An append-only Merkle tree that tracks only the minimal "frontier"
+/// needed to keep appending: for each level, the most recently completed
+/// left sibling. Right siblings that haven't been appended yet default to
+/// precomputed zero hashes, so the tree never needs to retain the full set
+/// of leaves. Generic over the leaf/node [`Hasher`] the same way the
+/// binary's `MerkleTree` is.
+pub struct IncrementalMerkleTree<H: Hasher = Sha256Hasher> {
+    /// `zero_hashes[l]` is the hash of an empty subtree of height `l`.
+    zero_hashes: Vec<H::Output>,
+    /// `filled_subtrees[l]` is the most recently completed left-hand node
+    /// at level `l`, kept around until its right-hand pair is appended.
+    filled_subtrees: Vec<H::Output>,
+    next_index: u64,
+    root: H::Output,
+    last_leaf_hash: Option<H::Output>,
+}
+
+/// Tracks the authentication path for a single leaf as the tree it was
+/// created from keeps growing. Call [`IncrementalMerkleTree::witness`]
+/// right after appending the leaf to mark it, then feed every subsequent
+/// [`IncrementalMerkleTree::append`] call into [`IncrementalWitness::append`]
+/// as well to keep the path valid.
+pub struct IncrementalWitness<H: Hasher = Sha256Hasher> {
+    position: u64,
+    leaf_hash: H::Output,
+    /// Sibling hash per level; defaults to the zero hash until the real
+    /// sibling subtree is appended and `resolved[level]` flips to `true`.
+    path: Vec<H::Output>,
+    resolved: Vec<bool>,
+    cursor_index: u64,
+    frontier: Vec<H::Output>,
+    zero_hashes: Vec<H::Output>,
+}
+
+fn build_zero_hashes<H: Hasher>() -> Vec<H::Output> {
+    let mut zero_hashes = Vec::with_capacity(DEPTH + 1);
+    zero_hashes.push(H::empty_output());
+    for level in 1..=DEPTH {
+        let child = zero_hashes[level - 1].clone();
+        zero_hashes.push(H::hash_nodes(&child, &child, true));
+    }
+    zero_hashes
+}
+
+impl<H: Hasher> IncrementalMerkleTree<H> {
+    pub fn new() -> Self {
+        let zero_hashes = build_zero_hashes::<H>();
+        let root = zero_hashes[DEPTH].clone();
+        let filled_subtrees = zero_hashes[..DEPTH].to_vec();
+
+        IncrementalMerkleTree {
+            zero_hashes,
+            filled_subtrees,
+            next_index: 0,
+            root,
+            last_leaf_hash: None,
+        }
+    }
+
+    /// Appends `leaf`, filling zero-hash defaults for the as-yet-unused
+    /// right subtrees, and returns the index it was assigned.
+    pub fn append(&mut self, leaf: &str) -> u64 {
+        let index = self.next_index;
+        let leaf_hash = H::hash_leaf(leaf.as_bytes(), true);
+
+        let mut level_index = index;
+        let mut current_hash = leaf_hash.clone();
+        for level in 0..DEPTH {
+            if level_index % 2 == 0 {
+                self.filled_subtrees[level] = current_hash.clone();
+                current_hash = H::hash_nodes(&current_hash, &self.zero_hashes[level], true);
+            } else {
+                current_hash = H::hash_nodes(&self.filled_subtrees[level], &current_hash, true);
+            }
+            level_index /= 2;
+        }
+
+        self.root = current_hash;
+        self.last_leaf_hash = Some(leaf_hash);
+        self.next_index += 1;
+        index
+    }
+
+    pub fn root(&self) -> H::Output {
+        self.root.clone()
+    }
+
+    /// Marks the most recently appended leaf for witnessing. Returns `None`
+    /// if nothing has been appended yet.
+    pub fn witness(&self) -> Option<IncrementalWitness<H>> {
+        if self.next_index == 0 {
+            return None;
+        }
+        let position = self.next_index - 1;
+
+        let mut path = Vec::with_capacity(DEPTH);
+        let mut resolved = Vec::with_capacity(DEPTH);
+        for level in 0..DEPTH {
+            if (position >> level) & 1 == 1 {
+                // This leaf was a right child at this level, so its left
+                // sibling was already completed and is fixed forever.
+                path.push(self.filled_subtrees[level].clone());
+                resolved.push(true);
+            } else {
+                // The right sibling subtree has no real leaves yet.
+                path.push(self.zero_hashes[level].clone());
+                resolved.push(false);
+            }
+        }
+
+        Some(IncrementalWitness {
+            position,
+            leaf_hash: self.last_leaf_hash.clone().unwrap(),
+            path,
+            resolved,
+            cursor_index: self.next_index,
+            frontier: self.zero_hashes[..DEPTH].to_vec(),
+            zero_hashes: self.zero_hashes.clone(),
+        })
+    }
+}
+
+impl<H: Hasher> IncrementalWitness<H> {
+    /// Feeds a leaf appended to the source tree (after this witness was
+    /// created) into the witness, resolving any sibling levels it completes.
+    pub fn append(&mut self, leaf: &str) {
+        let index = self.cursor_index;
+        let mut level_index = index;
+        let mut current_hash = H::hash_leaf(leaf.as_bytes(), true);
+
+        for level in 0..DEPTH {
+            let entering_hash = current_hash.clone();
+
+            if !self.resolved[level] {
+                let sibling_block = (self.position >> level) ^ 1;
+                if level_index == sibling_block {
+                    // `entering_hash` is the sibling subtree's hash so far,
+                    // with its own not-yet-appended slots zero-padded -
+                    // exactly mirroring how the tree computes its own root.
+                    self.path[level] = entering_hash.clone();
+                    if (index + 1) % (1u64 << level) == 0 {
+                        self.resolved[level] = true;
+                    }
+                }
+            }
+
+            if level_index % 2 == 0 {
+                self.frontier[level] = entering_hash.clone();
+                current_hash = H::hash_nodes(&entering_hash, &self.zero_hashes[level], true);
+            } else {
+                current_hash = H::hash_nodes(&self.frontier[level], &entering_hash, true);
+            }
+
+            level_index /= 2;
+        }
+
+        self.cursor_index += 1;
+    }
+
+    /// The authentication path from this witness's leaf up to the root,
+    /// one sibling hash per level.
+    pub fn authentication_path(&self) -> Vec<H::Output> {
+        self.path.clone()
+    }
+
+    pub fn verify(&self, root: &H::Output) -> bool {
+        let mut current_hash = self.leaf_hash.clone();
+        for level in 0..DEPTH {
+            current_hash = if (self.position >> level) & 1 == 1 {
+                H::hash_nodes(&self.path[level], &current_hash, true)
+            } else {
+                H::hash_nodes(&current_hash, &self.path[level], true)
+            };
+        }
+        current_hash == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Keccak256Hasher;
+
+    #[test]
+    fn test_witness_stays_valid_as_tree_grows() {
+        let mut tree = IncrementalMerkleTree::<Sha256Hasher>::new();
+        tree.append("a");
+        tree.append("b");
+        tree.append("c");
+
+        let mut witness = tree.witness().unwrap();
+        assert!(witness.verify(&tree.root()));
+
+        for leaf in ["d", "e", "f", "g", "h"] {
+            tree.append(leaf);
+            witness.append(leaf);
+            assert!(witness.verify(&tree.root()));
+        }
+    }
+
+    #[test]
+    fn test_witness_of_sole_leaf_matches_root() {
+        let mut tree = IncrementalMerkleTree::<Sha256Hasher>::new();
+        tree.append("only");
+
+        let witness = tree.witness().unwrap();
+        assert_eq!(witness.authentication_path().len(), DEPTH);
+        assert!(witness.verify(&tree.root()));
+    }
+
+    #[test]
+    fn test_incremental_tree_over_keccak256_hasher() {
+        let mut tree = IncrementalMerkleTree::<Keccak256Hasher>::new();
+        tree.append("a");
+        tree.append("b");
+
+        let witness = tree.witness().unwrap();
+        assert!(witness.verify(&tree.root()));
+    }
+}
@@ -0,0 +1,12 @@
+//! Library surface for the sparse and incremental Merkle tree subsystems,
+//! so they're real public API reachable from outside their own tests
+//! rather than code that only happens to compile because the crate's
+//! binary target links it in.
+//!
+//! The classic binary tree in `main.rs` stays a binary-only implementation
+//! detail for now; only the standalone subsystems built on top of
+//! [`hasher::Hasher`] are exposed here.
+
+pub mod hasher;
+pub mod incremental;
+pub mod sparse;
@@ -1,173 +1,354 @@
+use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 
+use merkle_tree_rs::hasher::{self, Hasher, Sha256Hasher};
+use merkle_tree_rs::{incremental, sparse};
 use sha2::{Digest, Sha256};
 
+/// Which side of the pair a proof's sibling hash sits on, relative to the
+/// hash it will be combined with while walking up to the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProofSide {
+    Left,
+    Right,
+}
+
+/// One step of an inclusion proof: a sibling hash together with which side
+/// of the pair it occupies, so `verify_proof` can recombine hashes in the
+/// same order they were originally combined in `MerkleTree::create`.
 #[derive(Clone, Debug)]
-struct MerkelNode {
-    data: String,
-    left: Option<Box<MerkelNode>>,
-    right: Option<Box<MerkelNode>>,
+struct ProofElement<H: Hasher> {
+    hash: H::Output,
+    side: ProofSide,
 }
 
-impl MerkelNode {
-    fn new(data: String) -> Self {
-        return MerkelNode {
-            data,
-            left: None,
-            right: None,
-        };
-    }
+/// One replay instruction for reconstructing a [`MultiProof`]'s root: where
+/// the next value comes from, or that it's time to combine the two most
+/// recently produced values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MultiProofOp {
+    /// Consume the next hash from the proven leaves, in the tree's
+    /// left-to-right order (not necessarily the caller's order).
+    Leaf,
+    /// Consume the next sibling hash that couldn't be derived from the
+    /// other leaves.
+    Proof,
+    /// Pop the two most recently produced values and push
+    /// `H::hash_nodes(left, right)`.
+    Combine,
+    /// Pop the one most recently produced value and push
+    /// `H::hash_nodes(value, value)`, for a lone node that was paired with
+    /// a duplicate of itself because its level had odd length.
+    CombineSelf,
 }
 
-type MerkelNodePair = (MerkelNode, MerkelNode);
+/// A proof that several leaves are all included under the same root,
+/// sharing any sibling hash that a naive "one proof per leaf" scheme would
+/// otherwise repeat. Produced by [`MerkleTree::get_multiproof`] and checked
+/// by [`MerkleTree::verify_multiproof`].
+#[derive(Clone, Debug)]
+struct MultiProof<H: Hasher> {
+    /// The proven leaf hashes, in the tree's left-to-right traversal order.
+    leaves: Vec<H::Output>,
+    /// Sibling hashes needed to reconstruct the root that aren't
+    /// recomputable from two already-included children.
+    proof: Vec<H::Output>,
+    /// Replay instructions recorded during the same traversal that
+    /// collected `leaves` and `proof`.
+    ops: Vec<MultiProofOp>,
+}
 
 #[derive(Debug)]
-struct MerkleTree {
-    root: Box<MerkelNode>,
+struct MerkleTree<H: Hasher = Sha256Hasher> {
+    /// `levels[0]` holds the leaf hashes; `levels[d + 1]` holds the parent
+    /// of each adjacent pair in `levels[d]`, with a lone trailing element
+    /// paired with a duplicate of itself when `levels[d]` has odd length;
+    /// `levels.last()` is the single-element root level. A flat `Vec` of
+    /// level arrays rather than a recursive node graph, so a proof is an
+    /// index walk (`sibling = i ^ 1`, `parent = i >> 1`) instead of a tree
+    /// walk, with no per-node cloning along the way.
+    levels: Vec<Vec<H::Output>>,
+    /// When `true` (the default), leaf and internal node hashes are
+    /// domain-separated via `MERKLE_LEAF_PREFIX`/`MERKLE_NODE_PREFIX` to
+    /// prevent second-preimage attacks. Disabling this reproduces the
+    /// original prefix-free hashing for backward compatibility only.
+    domain_separated: bool,
 }
 
-fn sha256_hash<T: AsRef<[u8]>>(input: T) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(input);
-    let result = hasher.finalize();
-    format!("{:x}", result)
+/// Index of `i`'s sibling within a level of length `level_len`: `i ^ 1`, or
+/// `i` itself when that would fall outside the level (the lone trailing
+/// leaf of an odd-length level, implicitly paired with a duplicate of
+/// itself).
+fn sibling_index(i: usize, level_len: usize) -> usize {
+    if i ^ 1 < level_len {
+        i ^ 1
+    } else {
+        i
+    }
 }
 
-impl MerkleTree {
+impl<H: Hasher> MerkleTree<H> {
     fn new() -> Self {
-        return MerkleTree {
-            root: Box::new(MerkelNode::new("dummy".to_owned())),
-        };
-    }
-    fn create<T: AsRef<[u8]> + Clone + Display + Debug>(&mut self, data: &mut Vec<T>) {
-        let mut leaves: Vec<String> = vec![];
-        if data.len() % 2 != 0 {
-            data.push(data[data.len() - 1].clone());
+        MerkleTree {
+            levels: Vec::new(),
+            domain_separated: true,
         }
+    }
 
-        for i in data.iter() {
-            let hash = sha256_hash(i);
-            leaves.push(hash);
+    /// Reproduces the pre-domain-separation hashing scheme, where leaf and
+    /// internal node hashes are indistinguishable. Kept only so existing
+    /// proofs generated before domain separation was introduced keep
+    /// verifying; new integrations should use [`MerkleTree::new`].
+    fn new_without_domain_separation() -> Self {
+        MerkleTree {
+            levels: Vec::new(),
+            domain_separated: false,
         }
+    }
 
-        let mut merkle_node_pairs: Vec<MerkelNodePair> = vec![];
+    fn create<T: AsRef<[u8]> + Display + Debug>(&mut self, data: &[T]) {
+        let leaves: Vec<H::Output> = data
+            .iter()
+            .map(|i| H::hash_leaf(i.as_ref(), self.domain_separated))
+            .collect();
+        self.build_levels(leaves);
+    }
 
-        let mut i = 0;
-        while i < leaves.len() - 1 {
-            let pair = (
-                MerkelNode::new(leaves[i].clone()),
-                MerkelNode::new(leaves[i + 1].clone()),
-            );
-            merkle_node_pairs.push(pair);
-            i += 2;
-        }
+    fn build_levels(&mut self, leaves: Vec<H::Output>) {
+        self.levels = vec![leaves];
 
-        self.build_merkle_tree(merkle_node_pairs);
-    }
+        while self.levels.last().unwrap().len() > 1 {
+            let level = self.levels.last().unwrap();
+            let next_len = level.len().div_ceil(2);
 
-    fn build_merkle_tree(&mut self, merkle_pairs: Vec<MerkelNodePair>) {
-        let mut output_nodes: Vec<MerkelNode> = vec![];
+            let mut next = Vec::with_capacity(next_len);
+            for parent in 0..next_len {
+                let left = 2 * parent;
+                let right = sibling_index(left, level.len());
+                next.push(H::hash_nodes(&level[left], &level[right], self.domain_separated));
+            }
 
-        for i in merkle_pairs {
-            let mut concat_string = String::from(i.0.data.clone());
-            concat_string.push_str(&i.1.data);
+            self.levels.push(next);
+        }
+    }
 
-            let hash = sha256_hash(concat_string);
-            let mut new_node = MerkelNode::new(hash);
+    /// The tree's root hash. Panics if called before [`MerkleTree::create`].
+    fn root(&self) -> &H::Output {
+        &self.levels.last().expect("tree has not been built yet")[0]
+    }
 
-            new_node.left = Some(Box::new(i.0));
-            new_node.right = Some(Box::new(i.1));
+    /// The number of leaves the tree was built from.
+    fn leaf_count(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
 
-            output_nodes.push(new_node);
+    /// Builds an inclusion proof for the leaf at `index`, in the order it
+    /// was passed to `create`. This is an `O(log n)` walk up the flat level
+    /// arrays (`sibling = i ^ 1`, `parent = i >> 1`), unlike `get_proof`,
+    /// which must first locate the leaf by its hash.
+    fn get_proof_by_index(&self, mut index: usize) -> Option<Vec<ProofElement<H>>> {
+        if index >= self.leaf_count() {
+            return None;
         }
 
-        if output_nodes.len() == 1 {
-            self.root = Box::new(output_nodes.last().unwrap().clone());
-            return;
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = sibling_index(index, level.len());
+            proof.push(ProofElement {
+                hash: level[sibling].clone(),
+                side: if index % 2 == 0 { ProofSide::Right } else { ProofSide::Left },
+            });
+            index >>= 1;
         }
+        Some(proof)
+    }
 
-        if output_nodes.len() % 2 != 0 {
-            output_nodes.push(output_nodes.last().unwrap().to_owned());
-        }
-        let mut output_pairs: Vec<MerkelNodePair> = vec![];
+    fn get_proof(&self, data: &str) -> Option<Vec<ProofElement<H>>> {
+        let target = H::hash_leaf(data.as_bytes(), self.domain_separated);
+        let index = self.levels.first()?.iter().position(|leaf| *leaf == target)?;
+        self.get_proof_by_index(index)
+    }
 
-        let mut i = 0;
-        while i < output_nodes.len() - 1 {
-            let pair = (output_nodes[i].clone(), output_nodes[i + 1].clone());
-            output_pairs.push(pair);
-            i += 2;
+    fn verify_proof(&self, data: &str, proof: &[ProofElement<H>]) -> bool {
+        let mut current_hash = H::hash_leaf(data.as_bytes(), self.domain_separated);
+
+        for element in proof {
+            current_hash = match element.side {
+                ProofSide::Left => H::hash_nodes(&element.hash, &current_hash, self.domain_separated),
+                ProofSide::Right => H::hash_nodes(&current_hash, &element.hash, self.domain_separated),
+            };
         }
 
-        return self.build_merkle_tree(output_pairs);
+        current_hash == *self.root()
     }
 
-    fn get_proof(&self, data: &str) -> Option<Vec<String>> {
-        fn get_proof_helper(node: &MerkelNode, data: &str, proof: &mut Vec<String>) -> bool {
-            if node.left.is_none() && node.right.is_none() {
-                return node.data == sha256_hash(data);
-            }
+    /// Builds a proof that every entry in `data` is included under this
+    /// tree's root, walking the level arrays once and recording a sibling
+    /// hash only where it can't be recomputed from two already-proven
+    /// children. Returns `None` if any requested leaf isn't actually in the
+    /// tree, or if `data` contains a duplicate entry (one leaf hash can't
+    /// back two slots in the replay, so `verify_multiproof` would reject
+    /// the resulting proof against its own unreduced `data`).
+    fn get_multiproof(&self, data: &[&str]) -> Option<MultiProof<H>> {
+        let unique_count: HashSet<&str> = data.iter().copied().collect();
+        if unique_count.len() != data.len() {
+            return None;
+        }
+
+        // The partial result for a node that's "known" (reconstructable
+        // from the proven leaves plus the sibling hashes collected so far).
+        // A node that isn't known contributes nothing here; its hash is
+        // recorded by its parent instead, as a single proof entry, once
+        // it's clear whether the parent needs it at all.
+        struct Known<H: Hasher> {
+            leaves: Vec<H::Output>,
+            proof: Vec<H::Output>,
+            ops: Vec<MultiProofOp>,
+        }
 
-            if let Some(ref left) = node.left {
-                if get_proof_helper(left, data, proof) {
-                    if let Some(ref right) = node.right {
-                        proof.push(right.data.clone());
-                    }
-                    return true;
+        fn collect<H: Hasher>(
+            levels: &[Vec<H::Output>],
+            depth: usize,
+            index: usize,
+            targets: &HashSet<H::Output>,
+            found: &mut HashSet<H::Output>,
+        ) -> Option<Known<H>> {
+            if depth == 0 {
+                let hash = &levels[0][index];
+                if targets.contains(hash) {
+                    found.insert(hash.clone());
+                    return Some(Known {
+                        leaves: vec![hash.clone()],
+                        proof: vec![],
+                        ops: vec![MultiProofOp::Leaf],
+                    });
                 }
+                return None;
             }
 
-            if let Some(ref right) = node.right {
-                if get_proof_helper(right, data, proof) {
-                    if let Some(ref left) = node.left {
-                        proof.push(left.data.clone());
-                    }
-                    return true;
-                }
+            let level_len = levels[depth - 1].len();
+            let left = 2 * index;
+            let right = sibling_index(left, level_len);
+
+            if right == left {
+                return collect(levels, depth - 1, left, targets, found).map(|mut known| {
+                    known.ops.push(MultiProofOp::CombineSelf);
+                    known
+                });
             }
 
-            false
+            let left_known = collect(levels, depth - 1, left, targets, found);
+            let right_known = collect(levels, depth - 1, right, targets, found);
+
+            match (left_known, right_known) {
+                (Some(mut l), Some(r)) => {
+                    l.leaves.extend(r.leaves);
+                    l.proof.extend(r.proof);
+                    l.ops.extend(r.ops);
+                    l.ops.push(MultiProofOp::Combine);
+                    Some(l)
+                }
+                (Some(mut l), None) => {
+                    l.proof.push(levels[depth - 1][right].clone());
+                    l.ops.push(MultiProofOp::Proof);
+                    l.ops.push(MultiProofOp::Combine);
+                    Some(l)
+                }
+                (None, Some(mut r)) => {
+                    r.proof.insert(0, levels[depth - 1][left].clone());
+                    let mut ops = vec![MultiProofOp::Proof];
+                    ops.append(&mut r.ops);
+                    ops.push(MultiProofOp::Combine);
+                    r.ops = ops;
+                    Some(r)
+                }
+                (None, None) => None,
+            }
         }
 
-        let mut proof = Vec::new();
-        if get_proof_helper(&self.root, data, &mut proof) {
-            Some(proof)
-        } else {
-            None
+        let targets: HashSet<H::Output> = data
+            .iter()
+            .map(|d| H::hash_leaf(d.as_bytes(), self.domain_separated))
+            .collect();
+
+        let mut found: HashSet<H::Output> = HashSet::new();
+        let depth = self.levels.len() - 1;
+        let known = collect::<H>(&self.levels, depth, 0, &targets, &mut found)?;
+
+        if found.len() != targets.len() {
+            return None;
         }
+
+        Some(MultiProof {
+            leaves: known.leaves,
+            proof: known.proof,
+            ops: known.ops,
+        })
     }
 
-    fn verify_proof(&self, data: &str, proof: &[String]) -> bool {
-        let mut current_hash = sha256_hash(data);
+    /// Verifies a [`MultiProof`] for `data` against this tree's root,
+    /// replaying `proof.ops` to fold the leaf and sibling hashes back up.
+    fn verify_multiproof(&self, data: &[&str], proof: &MultiProof<H>) -> bool {
+        let expected: HashSet<H::Output> = data
+            .iter()
+            .map(|d| H::hash_leaf(d.as_bytes(), self.domain_separated))
+            .collect();
+        let actual: HashSet<H::Output> = proof.leaves.iter().cloned().collect();
+        if expected.len() != data.len() || expected != actual {
+            return false;
+        }
 
-        for sibling in proof {
-            let mut concat_string = current_hash.clone();
-            concat_string.push_str(sibling);
-            current_hash = sha256_hash(concat_string);
+        // `proof` may have been handed to us by a party that didn't build
+        // it (that's the whole point of a multiproof), so a structurally
+        // malformed `ops` sequence - one that pops or consumes more than
+        // was produced - must fail closed rather than panic.
+        let mut stack: Vec<H::Output> = Vec::new();
+        let mut leaf_iter = proof.leaves.iter();
+        let mut proof_iter = proof.proof.iter();
+
+        for op in &proof.ops {
+            match op {
+                MultiProofOp::Leaf => match leaf_iter.next() {
+                    Some(leaf) => stack.push(leaf.clone()),
+                    None => return false,
+                },
+                MultiProofOp::Proof => match proof_iter.next() {
+                    Some(sibling) => stack.push(sibling.clone()),
+                    None => return false,
+                },
+                MultiProofOp::Combine => {
+                    let (Some(right), Some(left)) = (stack.pop(), stack.pop()) else {
+                        return false;
+                    };
+                    stack.push(H::hash_nodes(&left, &right, self.domain_separated));
+                }
+                MultiProofOp::CombineSelf => {
+                    let Some(value) = stack.pop() else {
+                        return false;
+                    };
+                    stack.push(H::hash_nodes(&value, &value, self.domain_separated));
+                }
+            }
         }
 
-        current_hash == self.root.data
+        stack.len() == 1 && stack[0] == *self.root() && leaf_iter.next().is_none() && proof_iter.next().is_none()
     }
 }
 
-fn print_tree(tree: &MerkleTree) {
-    fn print_tree_helper(node: &MerkelNode, prefix: String, is_left: bool) {
-        println!(
-            "{}{}{}",
-            prefix,
-            if is_left { "├──" } else { "└──" },
-            reduce_string(node.data.clone())
-        );
-
-        let new_prefix = format!("{}{}", prefix, if is_left { "│   " } else { "    " });
-        if let Some(ref left) = node.left {
-            print_tree_helper(left, new_prefix.clone(), true);
-        }
-        if let Some(ref right) = node.right {
-            print_tree_helper(right, new_prefix, false);
-        }
+fn print_tree<H: Hasher>(tree: &MerkleTree<H>) {
+    for (depth, level) in tree.levels.iter().enumerate().rev() {
+        let label = if depth == tree.levels.len() - 1 {
+            "root".to_owned()
+        } else {
+            format!("level {}", depth)
+        };
+        let hashes: Vec<String> = level.iter().map(|hash| reduce_string(to_hex(hash))).collect();
+        println!("{}: {}", label, hashes.join(" "));
     }
+}
 
-    print_tree_helper(&tree.root, String::new(), false);
+fn to_hex<T: AsRef<[u8]>>(data: T) -> String {
+    data.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
 }
 
 fn reduce_string(input: String) -> String {
@@ -185,12 +366,13 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use hasher::{Keccak256Hasher, TruncatedSha256Hasher};
 
     #[test]
     fn test_merkle_tree() {
-        let mut merkle_tree = MerkleTree::new();
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
 
-        let mut data = vec![
+        let data = vec![
             "a".to_owned(),
             "b".to_owned(),
             "c".to_owned(),
@@ -200,11 +382,11 @@ mod tests {
             "g".to_owned(),
             "h".to_owned(),
         ];
-    
-        merkle_tree.create(&mut data);
-    
+
+        merkle_tree.create(&data);
+
         print_tree(&merkle_tree);
-    
+
         let proof = merkle_tree.get_proof("c");
         if let Some(proof) = proof {
             println!("Inclusion proof for 'c': {:?}", proof);
@@ -213,7 +395,7 @@ mod tests {
         } else {
             println!("Data 'c' not found in the tree");
         }
-    
+
         let exclusion_proof = merkle_tree.get_proof("x");
         if let Some(proof) = exclusion_proof {
             println!("Exclusion proof for 'x': {:?}", proof);
@@ -223,4 +405,199 @@ mod tests {
             println!("Data 'x' is not in the tree (as expected)");
         }
     }
+
+    #[test]
+    fn test_domain_separated_leaf_cannot_be_forged_as_node() {
+        let data = vec!["a".to_owned(), "b".to_owned()];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        let leaf_hash = Sha256Hasher::hash_leaf(b"a", true);
+        let node_hash = Sha256Hasher::hash_nodes(&leaf_hash, &leaf_hash, true);
+
+        // An attacker presenting an internal node's concatenated children as
+        // leaf data must not hash to that same internal node's value.
+        let mut forged_leaf = leaf_hash.clone();
+        forged_leaf.extend_from_slice(&leaf_hash);
+        assert_ne!(Sha256Hasher::hash_leaf(&forged_leaf, true), node_hash);
+
+        let proof = merkle_tree.get_proof("a").unwrap();
+        assert!(merkle_tree.verify_proof("a", &proof));
+    }
+
+    #[test]
+    fn test_legacy_mode_reproduces_prefix_free_hashing() {
+        let data = vec!["a".to_owned(), "b".to_owned()];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new_without_domain_separation();
+        merkle_tree.create(&data);
+
+        let proof = merkle_tree.get_proof("a").unwrap();
+        assert!(merkle_tree.verify_proof("a", &proof));
+
+        // Without domain separation, a node hash is just SHA-256 of the
+        // concatenated raw child digests, with no prefix byte.
+        let leaf_hash = |data: &str| Sha256::digest(data.as_bytes()).to_vec();
+        let mut concat = leaf_hash("a");
+        concat.extend_from_slice(&leaf_hash("b"));
+        let expected_root = Sha256::digest(&concat).to_vec();
+        assert_eq!(*merkle_tree.root(), expected_root);
+    }
+
+    #[test]
+    fn test_verify_proof_for_right_sibling_leaf() {
+        // "b" is the right-hand leaf of the ("a", "b") pair, so its proof
+        // element is a *left* sibling and must be combined as
+        // H(sibling || current), not H(current || sibling).
+        let data = vec!["a".to_owned(), "b".to_owned()];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        let proof = merkle_tree.get_proof("b").unwrap();
+        assert_eq!(proof.len(), 1);
+        assert_eq!(proof[0].side, ProofSide::Left);
+        assert!(merkle_tree.verify_proof("b", &proof));
+    }
+
+    #[test]
+    fn test_keccak256_and_truncated_hashers_round_trip() {
+        let data = vec!["a".to_owned(), "b".to_owned(), "c".to_owned(), "d".to_owned()];
+
+        let mut keccak_tree = MerkleTree::<Keccak256Hasher>::new();
+        keccak_tree.create(&data);
+        let keccak_proof = keccak_tree.get_proof("c").unwrap();
+        assert!(keccak_tree.verify_proof("c", &keccak_proof));
+
+        let mut truncated_tree = MerkleTree::<TruncatedSha256Hasher>::new();
+        truncated_tree.create(&data);
+        assert_eq!(truncated_tree.root().len(), 20);
+        let truncated_proof = truncated_tree.get_proof("c").unwrap();
+        assert!(truncated_tree.verify_proof("c", &truncated_proof));
+    }
+
+    #[test]
+    fn test_multiproof_dedupes_shared_siblings_and_verifies() {
+        let data = vec![
+            "a".to_owned(),
+            "b".to_owned(),
+            "c".to_owned(),
+            "d".to_owned(),
+            "e".to_owned(),
+            "f".to_owned(),
+            "g".to_owned(),
+            "h".to_owned(),
+        ];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        // "a" and "b" are siblings, so the multiproof for both should never
+        // need a proof entry for either one's direct sibling, unlike two
+        // independent single-leaf proofs.
+        let proof = merkle_tree.get_multiproof(&["a", "b"]).unwrap();
+        assert!(merkle_tree.verify_multiproof(&["a", "b"], &proof));
+        assert!(!merkle_tree.verify_multiproof(&["a", "c"], &proof));
+
+        let single_proof_len = merkle_tree.get_proof("a").unwrap().len() + merkle_tree.get_proof("b").unwrap().len();
+        assert!(proof.proof.len() < single_proof_len);
+    }
+
+    #[test]
+    fn test_multiproof_for_leaves_spanning_different_subtrees() {
+        let data = vec![
+            "a".to_owned(),
+            "b".to_owned(),
+            "c".to_owned(),
+            "d".to_owned(),
+            "e".to_owned(),
+            "f".to_owned(),
+            "g".to_owned(),
+            "h".to_owned(),
+        ];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        let proof = merkle_tree.get_multiproof(&["c", "f", "h"]).unwrap();
+        assert!(merkle_tree.verify_multiproof(&["c", "f", "h"], &proof));
+        assert!(merkle_tree.verify_multiproof(&["h", "c", "f"], &proof));
+    }
+
+    #[test]
+    fn test_verify_multiproof_rejects_malformed_ops_instead_of_panicking() {
+        let data = vec!["a".to_owned(), "b".to_owned()];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        // A `Combine` with only one value pushed, and more `Leaf`/`Proof` ops
+        // than there are entries to back them - an adversarial verifier
+        // must reject this, not panic.
+        let malformed = MultiProof {
+            leaves: vec![Sha256Hasher::hash_leaf(b"a", true)],
+            proof: vec![],
+            ops: vec![MultiProofOp::Leaf, MultiProofOp::Combine],
+        };
+        assert!(!merkle_tree.verify_multiproof(&["a"], &malformed));
+
+        let trailing_ops = MultiProof {
+            leaves: vec![
+                Sha256Hasher::hash_leaf(b"a", true),
+                Sha256Hasher::hash_leaf(b"b", true),
+            ],
+            proof: vec![],
+            ops: vec![MultiProofOp::Leaf, MultiProofOp::Leaf, MultiProofOp::Proof],
+        };
+        assert!(!merkle_tree.verify_multiproof(&["a", "b"], &trailing_ops));
+    }
+
+    #[test]
+    fn test_multiproof_missing_leaf_returns_none() {
+        let data = vec!["a".to_owned(), "b".to_owned()];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        assert!(merkle_tree.get_multiproof(&["a", "x"]).is_none());
+    }
+
+    #[test]
+    fn test_multiproof_rejects_duplicate_request() {
+        let data = vec!["a".to_owned(), "b".to_owned()];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        // A duplicate entry can't be told apart from a single occurrence by
+        // `verify_multiproof`, which checks `data.len()` against the
+        // deduped leaf set - so an honest proof for it could never verify.
+        assert!(merkle_tree.get_multiproof(&["a", "a"]).is_none());
+    }
+
+    #[test]
+    fn test_get_proof_by_index_matches_get_proof() {
+        let data = vec![
+            "a".to_owned(),
+            "b".to_owned(),
+            "c".to_owned(),
+            "d".to_owned(),
+            "e".to_owned(),
+        ];
+
+        let mut merkle_tree = MerkleTree::<Sha256Hasher>::new();
+        merkle_tree.create(&data);
+
+        assert_eq!(merkle_tree.leaf_count(), 5);
+
+        for (index, value) in data.iter().enumerate() {
+            let by_index = merkle_tree.get_proof_by_index(index).unwrap();
+            let by_value = merkle_tree.get_proof(value).unwrap();
+            assert_eq!(by_index.len(), by_value.len());
+            assert!(merkle_tree.verify_proof(value, &by_index));
+        }
+
+        assert!(merkle_tree.get_proof_by_index(data.len()).is_none());
+    }
 }
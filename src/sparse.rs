@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::hasher::{Hasher, Sha256Hasher};
+
+/// Depth of the tree in bits, i.e. one level per bit of a SHA-256 digest.
+/// Every key is routed to a fixed leaf position by its hash, so unlike
+/// `MerkleTree` the tree never needs rebalancing as keys are inserted.
+/// This addressing is independent of `H`: it's how a key maps to a leaf
+/// position, not the hash function the tree hashes node values with.
+const DEPTH: usize = 256;
+
+/// A sparse Merkle tree, generic over the leaf/node [`Hasher`] the same way
+/// the binary's `MerkleTree` (in `main.rs`) is, keyed by the SHA-256 hash of each key. Empty
+/// subtrees collapse to precomputed "zero hashes" instead of being stored,
+/// so the tree stays light even though it conceptually has 2^256 leaves.
+///
+/// Unlike `MerkleTree::get_proof` in `main.rs`, which returns `None` for a
+/// missing leaf, `prove` always returns an authentication path: the same
+/// path proves membership when the leaf value matches, or non-membership
+/// when the leaf is the default zero hash.
+pub struct SparseMerkleTree<H: Hasher = Sha256Hasher> {
+    /// `zero_hashes[h]` is the root hash of an empty subtree of height `h`
+    /// above the leaf level, i.e. `zero_hashes[0]` is the default (empty)
+    /// leaf hash and `zero_hashes[DEPTH]` is the root of a fully empty tree.
+    zero_hashes: Vec<H::Output>,
+    /// Only the nodes that differ from the default zero hash are stored,
+    /// keyed by `(depth_from_root, path_prefix)`.
+    nodes: HashMap<(usize, Vec<bool>), H::Output>,
+    root: H::Output,
+}
+
+/// An authentication path proving either membership or non-membership of a
+/// key, depending on whether `leaf_hash` matches a real value's hash or the
+/// tree's default empty-leaf hash.
+#[derive(Clone, Debug)]
+pub struct SparseMerkleProof<H: Hasher = Sha256Hasher> {
+    key_bits: Vec<bool>,
+    leaf_hash: H::Output,
+    /// Sibling hashes from the leaf level up to the root, one per bit of
+    /// `key_bits`.
+    siblings: Vec<H::Output>,
+}
+
+fn key_to_bits(key: &str) -> Vec<bool> {
+    let digest = Sha256::digest(key.as_bytes());
+    let mut bits = Vec::with_capacity(DEPTH);
+    for byte in digest {
+        for i in (0..8).rev() {
+            bits.push((byte >> i) & 1 == 1);
+        }
+    }
+    bits
+}
+
+impl<H: Hasher> SparseMerkleTree<H> {
+    pub fn new() -> Self {
+        let mut zero_hashes = Vec::with_capacity(DEPTH + 1);
+        zero_hashes.push(H::empty_output());
+        for height in 1..=DEPTH {
+            let child = zero_hashes[height - 1].clone();
+            zero_hashes.push(H::hash_nodes(&child, &child, true));
+        }
+        let root = zero_hashes[DEPTH].clone();
+
+        SparseMerkleTree {
+            zero_hashes,
+            nodes: HashMap::new(),
+            root,
+        }
+    }
+
+    fn node_hash(&self, depth: usize, prefix: &[bool]) -> H::Output {
+        match self.nodes.get(&(depth, prefix.to_vec())) {
+            Some(hash) => hash.clone(),
+            None => self.zero_hashes[DEPTH - depth].clone(),
+        }
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        let bits = key_to_bits(key);
+        let leaf_hash = H::hash_leaf(value.as_bytes(), true);
+        self.nodes.insert((DEPTH, bits.clone()), leaf_hash.clone());
+
+        let mut current_hash = leaf_hash;
+        for depth in (1..=DEPTH).rev() {
+            let bit = bits[depth - 1];
+            let mut sibling_prefix = bits[..depth].to_vec();
+            let last = sibling_prefix.len() - 1;
+            sibling_prefix[last] = !bit;
+            let sibling_hash = self.node_hash(depth, &sibling_prefix);
+
+            current_hash = if bit {
+                H::hash_nodes(&sibling_hash, &current_hash, true)
+            } else {
+                H::hash_nodes(&current_hash, &sibling_hash, true)
+            };
+
+            self.nodes
+                .insert((depth - 1, bits[..depth - 1].to_vec()), current_hash.clone());
+        }
+
+        self.root = current_hash;
+    }
+
+    pub fn get_root(&self) -> H::Output {
+        self.root.clone()
+    }
+
+    pub fn prove(&self, key: &str) -> SparseMerkleProof<H> {
+        let bits = key_to_bits(key);
+        let leaf_hash = self.node_hash(DEPTH, &bits);
+
+        let mut siblings = Vec::with_capacity(DEPTH);
+        for depth in (1..=DEPTH).rev() {
+            let bit = bits[depth - 1];
+            let mut sibling_prefix = bits[..depth].to_vec();
+            let last = sibling_prefix.len() - 1;
+            sibling_prefix[last] = !bit;
+            siblings.push(self.node_hash(depth, &sibling_prefix));
+        }
+
+        SparseMerkleProof {
+            key_bits: bits,
+            leaf_hash,
+            siblings,
+        }
+    }
+}
+
+impl<H: Hasher> SparseMerkleProof<H> {
+    /// Verifies this path against `root`. Pass `Some(value)` to check
+    /// membership of `key -> value`, or `None` to check that `key` is
+    /// absent from the tree (a genuine non-membership proof, not just a
+    /// lookup miss).
+    pub fn verify(&self, root: &H::Output, expected_value: Option<&str>) -> bool {
+        let expected_leaf_hash = match expected_value {
+            Some(value) => H::hash_leaf(value.as_bytes(), true),
+            None => H::empty_output(),
+        };
+        if expected_leaf_hash != self.leaf_hash {
+            return false;
+        }
+
+        let mut current_hash = self.leaf_hash.clone();
+        for (i, sibling) in self.siblings.iter().enumerate() {
+            let depth = self.key_bits.len() - i;
+            let bit = self.key_bits[depth - 1];
+            current_hash = if bit {
+                H::hash_nodes(sibling, &current_hash, true)
+            } else {
+                H::hash_nodes(&current_hash, sibling, true)
+            };
+        }
+
+        current_hash == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Keccak256Hasher;
+
+    #[test]
+    fn test_membership_and_non_membership_proofs() {
+        let mut tree = SparseMerkleTree::<Sha256Hasher>::new();
+        tree.insert("alice", "100");
+        tree.insert("bob", "50");
+
+        let root = tree.get_root();
+
+        let alice_proof = tree.prove("alice");
+        assert!(alice_proof.verify(&root, Some("100")));
+        assert!(!alice_proof.verify(&root, Some("999")));
+
+        let carol_proof = tree.prove("carol");
+        assert!(carol_proof.verify(&root, None));
+        assert!(!carol_proof.verify(&root, Some("0")));
+    }
+
+    #[test]
+    fn test_empty_tree_has_stable_root() {
+        let tree = SparseMerkleTree::<Sha256Hasher>::new();
+        let proof = tree.prove("anything");
+        assert!(proof.verify(&tree.get_root(), None));
+    }
+
+    #[test]
+    fn test_sparse_tree_over_keccak256_hasher() {
+        let mut tree = SparseMerkleTree::<Keccak256Hasher>::new();
+        tree.insert("alice", "100");
+
+        let root = tree.get_root();
+        let proof = tree.prove("alice");
+        assert!(proof.verify(&root, Some("100")));
+        assert!(!proof.verify(&root, None));
+    }
+}